@@ -0,0 +1,311 @@
+//! Conversion between Nintendo DOL executables and big-endian PowerPC ELF
+//! files, mirroring decomp-toolkit's `elf2dol`.
+
+use byteorder::{WriteBytesExt, BE};
+use object::{Architecture, Endianness, Object, ObjectKind, ObjectSection};
+use object::SectionKind as ObjSectionKind;
+use std::io::Write;
+
+use {DolFile, Error, Section, SectionKind};
+
+const EI_NIDENT: usize = 16;
+const ET_EXEC: u16 = 2;
+const EM_PPC: u16 = 20;
+const EV_CURRENT: u32 = 1;
+const PT_LOAD: u32 = 1;
+const SHT_NULL: u32 = 0;
+const SHT_PROGBITS: u32 = 1;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOBITS: u32 = 8;
+const SHF_WRITE: u32 = 1;
+const SHF_ALLOC: u32 = 2;
+const SHF_EXECINSTR: u32 = 4;
+
+/// Converts a big-endian PowerPC executable ELF into a [DolFile].
+///
+/// Allocatable sections are classified as [SectionKind::Text] when executable
+/// and [SectionKind::Data] otherwise. Uninitialized-data sections (`.bss`,
+/// `.sbss`, `.sbss2`, ...) are detected by their ELF section kind and folded
+/// into a single contiguous `bss_start`/`bss_length` span rather than carried
+/// as sections, matching how [DolFile] itself represents BSS. Enforces the
+/// same 7 text / 11 data section limits as [DolFile::write].
+pub fn elf_to_dol(data: &[u8]) -> Result<DolFile, Error> {
+    let obj = object::File::parse(data)?;
+
+    if obj.kind() != ObjectKind::Executable {
+        return Err(Error::NotAnExecutable);
+    }
+
+    if obj.architecture() != Architecture::PowerPc || obj.endianness() != Endianness::Big {
+        return Err(Error::UnsupportedArchitecture);
+    }
+
+    let mut sections = Vec::new();
+    let mut bss_start: Option<u32> = None;
+    let mut bss_end: u32 = 0;
+    let mut next_text_index = 0u8;
+    let mut next_data_index = 7u8;
+
+    for section in obj.sections() {
+        let address = section.address() as u32;
+        let size = section.size() as u32;
+        if address == 0 || size == 0 {
+            continue;
+        }
+
+        if section.kind() == ObjSectionKind::UninitializedData {
+            bss_start = Some(bss_start.map_or(address, |start| start.min(address)));
+            bss_end = bss_end.max(address + size);
+            continue;
+        }
+
+        let kind = match section.kind() {
+            ObjSectionKind::Text => SectionKind::Text,
+            _ => SectionKind::Data,
+        };
+
+        // Assigns header slots in the same order DolFile::write packs them
+        // into, since this DOL has no prior on-disk layout of its own yet.
+        let index = match kind {
+            SectionKind::Text => {
+                let index = next_text_index;
+                next_text_index += 1;
+                index
+            }
+            SectionKind::Data => {
+                let index = next_data_index;
+                next_data_index += 1;
+                index
+            }
+        };
+
+        sections.push(Section {
+            kind,
+            address,
+            data: section.data()?.to_vec(),
+            index,
+        });
+    }
+
+    let text_count = sections.iter().filter(|s| s.kind == SectionKind::Text).count();
+    if text_count > 7 {
+        return Err(Error::TooManySections(SectionKind::Text));
+    }
+
+    let data_count = sections.iter().filter(|s| s.kind == SectionKind::Data).count();
+    if data_count > 11 {
+        return Err(Error::TooManySections(SectionKind::Data));
+    }
+
+    let dol = DolFile {
+        sections,
+        bss_start: bss_start.unwrap_or(0),
+        bss_length: bss_start.map_or(0, |start| bss_end - start),
+        entry_point: obj.entry() as u32,
+    };
+    dol.validate()?;
+
+    Ok(dol)
+}
+
+/// Converts a [DolFile] into a minimal ELF32 big-endian PowerPC executable,
+/// with one program header and one section per DOL section (plus, if there is
+/// BSS, a trailing `PT_LOAD` program header with `filesz` 0 and `memsz`
+/// `bss_length` so a standard loader maps and zeroes it, and a matching
+/// `SHT_NOBITS` section for tooling), so the output can be round-tripped
+/// through standard ELF tooling.
+pub fn dol_to_elf(dol: &DolFile) -> Result<Vec<u8>, Error> {
+    const EHSIZE: u64 = 52;
+    const PHENTSIZE: u64 = 32;
+    const SHENTSIZE: u64 = 40;
+
+    let has_bss = dol.bss_length > 0;
+    let phnum = dol.sections.len() + (has_bss as usize);
+    let shnum = 1 + dol.sections.len() + (has_bss as usize) + 1; // null + sections [+ .bss] + .shstrtab
+
+    let phoff = EHSIZE;
+    let mut data_offset = phoff + phnum as u64 * PHENTSIZE;
+
+    // Lay out section data immediately after the program headers.
+    let mut section_offsets = Vec::with_capacity(dol.sections.len());
+    for section in &dol.sections {
+        section_offsets.push(data_offset);
+        data_offset += section.data.len() as u64;
+    }
+
+    // `.shstrtab` holds the name strings for every section header.
+    let mut shstrtab = vec![0u8]; // index 0 is the empty name
+    let mut name_offsets = Vec::with_capacity(dol.sections.len());
+    for (i, section) in dol.sections.iter().enumerate() {
+        name_offsets.push(shstrtab.len() as u32);
+        let name = match section.kind {
+            SectionKind::Text => format!(".text{}\0", i),
+            SectionKind::Data => format!(".data{}\0", i),
+        };
+        shstrtab.extend_from_slice(name.as_bytes());
+    }
+    let bss_name_offset = shstrtab.len() as u32;
+    if has_bss {
+        shstrtab.extend_from_slice(b".bss\0");
+    }
+    let shstrtab_name_offset = shstrtab.len() as u32;
+    shstrtab.extend_from_slice(b".shstrtab\0");
+
+    let shstrtab_offset = data_offset;
+    data_offset += shstrtab.len() as u64;
+    let shoff = data_offset;
+
+    let mut out = Vec::new();
+
+    // e_ident
+    out.extend_from_slice(&[0x7f, b'E', b'L', b'F', 1, 2, 1]);
+    out.resize(EI_NIDENT, 0);
+
+    out.write_u16::<BE>(ET_EXEC)?;
+    out.write_u16::<BE>(EM_PPC)?;
+    out.write_u32::<BE>(EV_CURRENT)?;
+    out.write_u32::<BE>(dol.entry_point)?;
+    out.write_u32::<BE>(phoff as u32)?;
+    out.write_u32::<BE>(shoff as u32)?;
+    out.write_u32::<BE>(0)?; // e_flags
+    out.write_u16::<BE>(EHSIZE as u16)?;
+    out.write_u16::<BE>(PHENTSIZE as u16)?;
+    out.write_u16::<BE>(phnum as u16)?;
+    out.write_u16::<BE>(SHENTSIZE as u16)?;
+    out.write_u16::<BE>(shnum as u16)?;
+    out.write_u16::<BE>((shnum - 1) as u16)?; // e_shstrndx
+
+    // Program headers: one PT_LOAD segment per DOL section.
+    for (section, &offset) in dol.sections.iter().zip(&section_offsets) {
+        let flags = match section.kind {
+            SectionKind::Text => 0x1 | 0x4, // PF_X | PF_R
+            SectionKind::Data => 0x2 | 0x4, // PF_W | PF_R
+        };
+
+        out.write_u32::<BE>(PT_LOAD)?;
+        out.write_u32::<BE>(offset as u32)?;
+        out.write_u32::<BE>(section.address)?;
+        out.write_u32::<BE>(section.address)?;
+        out.write_u32::<BE>(section.data.len() as u32)?;
+        out.write_u32::<BE>(section.data.len() as u32)?;
+        out.write_u32::<BE>(flags)?;
+        out.write_u32::<BE>(4)?; // p_align
+    }
+
+    if has_bss {
+        // BSS has no file contents (filesz 0) but must still be mapped and
+        // zeroed by the loader (memsz = bss_length), unlike the SHT_NOBITS
+        // section header below, which only describes it to tooling.
+        out.write_u32::<BE>(PT_LOAD)?;
+        out.write_u32::<BE>(0)?; // p_offset
+        out.write_u32::<BE>(dol.bss_start)?;
+        out.write_u32::<BE>(dol.bss_start)?;
+        out.write_u32::<BE>(0)?; // p_filesz
+        out.write_u32::<BE>(dol.bss_length)?;
+        out.write_u32::<BE>(0x2 | 0x4)?; // PF_W | PF_R
+        out.write_u32::<BE>(4)?; // p_align
+    }
+
+    // Section contents, back to back.
+    for section in &dol.sections {
+        out.write_all(&section.data)?;
+    }
+    out.write_all(&shstrtab)?;
+
+    // Section headers.
+    write_section_header(&mut out, 0, SHT_NULL, 0, 0, 0, 0)?; // null section
+
+    for (i, (section, &offset)) in dol.sections.iter().zip(&section_offsets).enumerate() {
+        let flags = match section.kind {
+            SectionKind::Text => SHF_ALLOC | SHF_EXECINSTR,
+            SectionKind::Data => SHF_ALLOC | SHF_WRITE,
+        };
+
+        write_section_header(
+            &mut out,
+            name_offsets[i],
+            SHT_PROGBITS,
+            flags,
+            section.address,
+            offset as u32,
+            section.data.len() as u32,
+        )?;
+    }
+
+    if has_bss {
+        // SHT_NOBITS: .bss occupies no space in the file, only in memory.
+        write_section_header(
+            &mut out,
+            bss_name_offset,
+            SHT_NOBITS,
+            SHF_ALLOC | SHF_WRITE,
+            dol.bss_start,
+            shstrtab_offset as u32,
+            dol.bss_length,
+        )?;
+    }
+
+    write_section_header(
+        &mut out,
+        shstrtab_name_offset,
+        SHT_STRTAB,
+        0,
+        0,
+        shstrtab_offset as u32,
+        shstrtab.len() as u32,
+    )?;
+
+    Ok(out)
+}
+
+/// Writes a single ELF32 section header.
+fn write_section_header<W: WriteBytesExt>(
+    wtr: &mut W,
+    name: u32,
+    kind: u32,
+    flags: u32,
+    addr: u32,
+    offset: u32,
+    size: u32,
+) -> Result<(), Error> {
+    wtr.write_u32::<BE>(name)?;
+    wtr.write_u32::<BE>(kind)?;
+    wtr.write_u32::<BE>(flags)?;
+    wtr.write_u32::<BE>(addr)?;
+    wtr.write_u32::<BE>(offset)?;
+    wtr.write_u32::<BE>(size)?;
+    wtr.write_u32::<BE>(0)?; // sh_link
+    wtr.write_u32::<BE>(0)?; // sh_info
+    wtr.write_u32::<BE>(4)?; // sh_addralign
+    wtr.write_u32::<BE>(0)?; // sh_entsize
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dol_to_elf_round_trips_entry_and_sections() {
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x8000_0000, data: vec![0x4e, 0x80, 0x00, 0x20], index: 0},
+                Section {kind: SectionKind::Data, address: 0x8000_1000, data: vec![1, 2, 3, 4], index: 7},
+            ],
+            bss_start: 0x8000_2000,
+            bss_length: 0x10,
+            entry_point: 0x8000_0000,
+        };
+
+        let elf_bytes = dol_to_elf(&dol).expect("Could not convert DOL to ELF");
+
+        let obj = object::File::parse(&*elf_bytes).expect("Could not parse generated ELF");
+        assert_eq!(obj.entry(), 0x8000_0000);
+
+        let roundtripped = elf_to_dol(&elf_bytes).expect("Could not convert ELF back to DOL");
+        assert_eq!(roundtripped.entry_point, dol.entry_point);
+        assert_eq!(roundtripped.bss_start, dol.bss_start);
+        assert_eq!(roundtripped.bss_length, dol.bss_length);
+        assert_eq!(roundtripped.sections.len(), dol.sections.len());
+    }
+}
@@ -1,6 +1,7 @@
 //! Module holding our error type.
 
 use std::io;
+use object;
 use SectionKind;
 
 quick_error! {
@@ -17,5 +18,74 @@ quick_error! {
         TooManySections(kind: SectionKind) {
             display("Too many sections of kind {:?}", kind)
         }
+
+        /// The given address does not fall within any loaded section or BSS,
+        /// or, when applying a REL relocation, the given byte offset lies past
+        /// the end of the REL section being patched.
+        OutOfBounds(addr: u32) {
+            display("Address {:#010x} is out of bounds", addr)
+        }
+
+        /// Two sections' `[address, address + len)` ranges overlap one another.
+        OverlappingSections(a: u32, b: u32) {
+            display("Section at {:#010x} overlaps section at {:#010x}", a, b)
+        }
+
+        /// A DOL must contain at least one section.
+        NoSections {
+            display("DOL file has no sections")
+        }
+
+        /// The combined size of all sections would overflow or exceed the
+        /// addressable range of a DOL file.
+        SectionsTooLarge {
+            display("Sections are too large to fit in a DOL file")
+        }
+
+        /// An error was encountered parsing an ELF file.
+        Object(err: object::Error) {
+            from()
+            display("ELF error: {}", err)
+        }
+
+        /// The given ELF file is not an executable.
+        NotAnExecutable {
+            display("ELF file is not an executable")
+        }
+
+        /// The given ELF file is not for a big-endian PowerPC target.
+        UnsupportedArchitecture {
+            display("ELF file is not a big-endian PowerPC executable")
+        }
+
+        /// The given buffer does not start with the Yaz0 magic.
+        NotYaz0 {
+            display("Buffer is not Yaz0-compressed")
+        }
+
+        /// A Yaz0 back-reference's distance reaches further back than any
+        /// output produced so far.
+        InvalidBackReference(distance: usize) {
+            display("Yaz0 back-reference distance {} exceeds produced output", distance)
+        }
+
+        /// A REL relocation had a type byte that is not a known `R_PPC_*` or
+        /// `R_DOLPHIN_*` relocation.
+        UnknownRelocationKind(kind: u8) {
+            display("Unknown REL relocation kind {}", kind)
+        }
+
+        /// A REL relocation referenced a section that does not exist in its
+        /// target module.
+        UnknownRelocationSection(section: u8) {
+            display("REL relocation references unknown section {}", section)
+        }
+
+        /// A REL's relocation entries were not in non-decreasing offset order
+        /// within a section run, so their offsets cannot be delta-encoded on
+        /// write.
+        UnsortedRelocations {
+            display("REL relocations must be sorted by offset within each section run")
+        }
     }
 }
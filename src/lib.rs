@@ -2,12 +2,18 @@
 extern crate byteorder;
 #[macro_use]
 extern crate itertools;
+extern crate object;
 
-use byteorder::{ReadBytesExt, WriteBytesExt, BE};
-use std::io::{Read, Seek, SeekFrom, Write};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BE};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
+mod elf;
 mod error;
+mod rel;
+mod yaz0;
+pub use elf::{dol_to_elf, elf_to_dol};
 pub use error::Error;
+pub use rel::{RelFile, RelImport, RelSection, Relocation, RelocationKind};
 
 /// Indicates the type of a DOL section (text or data).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,6 +28,17 @@ pub struct Section {
     pub kind: SectionKind,
     pub address: u32,
     pub data: Vec<u8>,
+    /// The DOL header slot (0-6 for text, 7-17 for data) this section
+    /// occupies. [DolFile::parse] fills this in from the on-disk slot; REL
+    /// relocations' `target_section` numbers slots this way too, so
+    /// `RelFile::link_against` must resolve against `index`, not against
+    /// position in [DolFile::sections] (which drops empty slots and so does
+    /// not line up with it). Sections built directly rather than via `parse`
+    /// should set this to the slot they are meant to occupy.
+    /// [DolFile::write] does not read this field: it packs text and data
+    /// sections into consecutive slots in the order they appear, so a
+    /// section's slot in the written file can differ from `index`.
+    pub index: u8,
 }
 
 /// A DOL executable file.
@@ -103,19 +120,23 @@ impl DolHeader {
 }
 
 /// Loads sections into a [Vec] of [Section]s, given offsets, addresses, lengths, and the type of these sections.
+/// `base_index` is the DOL header slot of `offsets[0]`/`addresses[0]`/`lengths[0]`, so that each loaded
+/// [Section::index] reflects its true on-disk slot even though empty slots are filtered out.
 fn load_sections<R>(
     rdr: &mut R,
     offsets: &[u32],
     addresses: &[u32],
     lengths: &[u32],
     kind: SectionKind,
+    base_index: u8,
 ) -> Result<Vec<Section>, Error>
 where
     R: Read + Seek,
 {
     izip!(offsets, addresses, lengths)
-        .filter(|(_, _, &l)| l > 0)
-        .map(|(&offset, &address, &length)| {
+        .enumerate()
+        .filter(|(_, (_, _, &l))| l > 0)
+        .map(|(i, (&offset, &address, &length))| {
             let mut data = Vec::with_capacity(length as usize);
 
             rdr.seek(SeekFrom::Start(offset as u64))?;
@@ -125,6 +146,7 @@ where
                 kind,
                 address,
                 data,
+                index: base_index + i as u8,
             })
         })
         .collect()
@@ -143,6 +165,7 @@ impl DolFile {
             &header.section_addresses[0..7],
             &header.section_lengths[0..7],
             SectionKind::Text,
+            0,
         )?;
         sections.extend(load_sections(
             rdr,
@@ -150,14 +173,88 @@ impl DolFile {
             &header.section_addresses[7..18],
             &header.section_lengths[7..18],
             SectionKind::Data,
+            7,
         )?);
 
-        Ok(DolFile {
+        let dol = DolFile {
             sections,
             bss_start: header.bss_start,
             bss_length: header.bss_length,
             entry_point: header.entry_point,
-        })
+        };
+        dol.validate()?;
+
+        Ok(dol)
+    }
+
+    /// Validates this DOL's sections, returning an error if they are unusable:
+    /// a DOL with no sections at all ([Error::NoSections]), two sections whose
+    /// `[address, address + data.len())` ranges overlap
+    /// ([Error::OverlappingSections]), or sections whose combined length would
+    /// overflow or exceed the addressable range of a DOL file
+    /// ([Error::SectionsTooLarge]).
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.sections.is_empty() {
+            return Err(Error::NoSections);
+        }
+
+        let mut sorted: Vec<&Section> = self.sections.iter().collect();
+        sorted.sort_by_key(|s| s.address);
+
+        for pair in sorted.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let a_end = a.address as u64 + a.data.len() as u64;
+            if a_end > b.address as u64 {
+                return Err(Error::OverlappingSections(a.address, b.address));
+            }
+        }
+
+        let total_length = sorted
+            .iter()
+            .try_fold(0u64, |acc, s| acc.checked_add(s.data.len() as u64))
+            .ok_or(Error::SectionsTooLarge)?;
+
+        // section data is laid out starting at offset 0x100
+        let end = 0x100u64.checked_add(total_length).ok_or(Error::SectionsTooLarge)?;
+        if end > u32::max_value() as u64 {
+            return Err(Error::SectionsTooLarge);
+        }
+
+        Ok(())
+    }
+
+    /// Loads a DOL file from a reader, transparently decompressing it first if
+    /// it is Yaz0-compressed (as DOLs commonly are when stored in archives).
+    pub fn parse_maybe_compressed<R>(rdr: &mut R) -> Result<Self, Error>
+    where
+        R: Read + Seek,
+    {
+        let mut magic = [0u8; 4];
+        rdr.read_exact(&mut magic)?;
+        rdr.seek(SeekFrom::Start(0))?;
+
+        if &magic == yaz0::MAGIC {
+            let mut compressed = Vec::new();
+            rdr.read_to_end(&mut compressed)?;
+
+            let mut decompressed = Cursor::new(yaz0::decompress(&compressed)?);
+            Self::parse(&mut decompressed)
+        } else {
+            Self::parse(rdr)
+        }
+    }
+
+    /// Writes a DOL file to a writer, Yaz0-compressing it first.
+    pub fn write_compressed<W>(&self, wtr: &mut W) -> Result<(), Error>
+    where
+        W: Write + Seek,
+    {
+        let mut uncompressed = Cursor::new(Vec::new());
+        self.write(&mut uncompressed)?;
+
+        wtr.write_all(&yaz0::compress(&uncompressed.into_inner()))?;
+
+        Ok(())
     }
 
     /// Writes a DOL file to a writer, returning `Ok(())` if successful.
@@ -165,6 +262,8 @@ impl DolFile {
     where
         W: Write + Seek,
     {
+        self.validate()?;
+
         let text_sections: Vec<_> = self.sections
             .iter()
             .filter(|s| s.kind == SectionKind::Text)
@@ -230,6 +329,76 @@ impl DolFile {
 
         Ok(())
     }
+
+    /// Flattens this DOL's sections into a single contiguous buffer addressed by
+    /// PowerPC virtual address, mirroring ppc750cl's `Dol` memory model. The buffer
+    /// spans from the lowest section address to the highest `address + data.len()`
+    /// (extended to cover BSS, if any), so BSS addresses read back as zero.
+    pub fn to_memory(&self) -> Result<DolMemory<'_>, Error> {
+        if self.sections.is_empty() {
+            return Ok(DolMemory {
+                memory: Vec::new(),
+                memory_offset: 0,
+                sections: &self.sections,
+            });
+        }
+
+        let mut low = self.sections.iter().map(|s| s.address).min().unwrap();
+        let mut high = self.sections
+            .iter()
+            .map(|s| s.address as u64 + s.data.len() as u64)
+            .max()
+            .unwrap();
+
+        if self.bss_length > 0 {
+            low = low.min(self.bss_start);
+            high = high.max(self.bss_start as u64 + self.bss_length as u64);
+        }
+
+        let mut memory = vec![0u8; (high - low as u64) as usize];
+        for section in &self.sections {
+            let start = (section.address - low) as usize;
+            memory[start..start + section.data.len()].copy_from_slice(&section.data);
+        }
+
+        Ok(DolMemory {
+            memory,
+            memory_offset: low,
+            sections: &self.sections,
+        })
+    }
+}
+
+/// A flattened, contiguous view of a [DolFile]'s sections, addressed by PowerPC
+/// virtual address rather than by section. Returned by [DolFile::to_memory].
+#[derive(Debug)]
+pub struct DolMemory<'a> {
+    pub memory: Vec<u8>,
+    pub memory_offset: u32,
+    sections: &'a [Section],
+}
+
+impl<'a> DolMemory<'a> {
+    /// Reads a big-endian `u32` at the given virtual address.
+    pub fn read_u32_at(&self, addr: u32) -> Result<u32, Error> {
+        Ok(BE::read_u32(self.read_bytes_at(addr, 4)?))
+    }
+
+    /// Reads `len` bytes starting at the given virtual address.
+    pub fn read_bytes_at(&self, addr: u32, len: usize) -> Result<&[u8], Error> {
+        let start = addr.checked_sub(self.memory_offset)
+            .ok_or(Error::OutOfBounds(addr))? as usize;
+        let end = start.checked_add(len).ok_or(Error::OutOfBounds(addr))?;
+
+        self.memory.get(start..end).ok_or(Error::OutOfBounds(addr))
+    }
+
+    /// Returns the section, if any, that contains the given virtual address.
+    pub fn section_at(&self, addr: u32) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|s| addr >= s.address && (addr as u64) < s.address as u64 + s.data.len() as u64)
+    }
 }
 
 #[cfg(test)]
@@ -242,8 +411,8 @@ mod test {
         let mut cur = Cursor::new(Vec::new());
 
         let mut sections = Vec::new();
-        for _ in 0..8 {
-            sections.push(Section {address: 0x10, data: vec![1,3,3,7], kind: SectionKind::Text});
+        for i in 0..8 {
+            sections.push(Section {address: 0x10 + i * 4, data: vec![1,3,3,7], kind: SectionKind::Text, index: i as u8});
         }
 
         let hdr = DolFile {
@@ -261,8 +430,8 @@ mod test {
         let mut cur = Cursor::new(Vec::new());
         let mut sections = Vec::new();
 
-        for _ in 0..12 {
-            sections.push(Section {address: 0x10, data: vec![1,3,3,7], kind: SectionKind::Data});
+        for i in 0..12 {
+            sections.push(Section {address: 0x10 + i * 4, data: vec![1,3,3,7], kind: SectionKind::Data, index: 7 + i as u8});
         }
 
         let hdr = DolFile {
@@ -274,6 +443,83 @@ mod test {
         assert!(hdr.write(&mut cur).is_err(), "attempting to write too many data sections should cause an error");
     }
 
+    #[test]
+    fn rejects_overlapping_sections() {
+        let dol = DolFile {
+            sections: vec![
+                Section {address: 0x1000, data: vec![0; 0x10], kind: SectionKind::Text, index: 0},
+                Section {address: 0x1008, data: vec![0; 0x10], kind: SectionKind::Text, index: 1},
+            ],
+            bss_start: 0, bss_length: 0,
+            entry_point: 0x1000,
+        };
+
+        match dol.validate() {
+            Err(Error::OverlappingSections(0x1000, 0x1008)) => {}
+            other => panic!("expected OverlappingSections(0x1000, 0x1008), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_empty_dol() {
+        let dol = DolFile {
+            sections: Vec::new(),
+            bss_start: 0, bss_length: 0,
+            entry_point: 0,
+        };
+
+        match dol.validate() {
+            Err(Error::NoSections) => {}
+            other => panic!("expected NoSections, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trips_compressed_dol() {
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x1000, data: vec![0x60u8; 64], index: 0},
+            ],
+            bss_start: 0, bss_length: 0,
+            entry_point: 0x1000,
+        };
+
+        let mut compressed = Cursor::new(Vec::new());
+        dol.write_compressed(&mut compressed).expect("Could not write compressed DOL");
+
+        let mut compressed = Cursor::new(compressed.into_inner());
+        let roundtripped = DolFile::parse_maybe_compressed(&mut compressed)
+            .expect("Could not parse compressed DOL");
+
+        assert_eq!(roundtripped.entry_point, dol.entry_point);
+        assert_eq!(roundtripped.sections[0].data, dol.sections[0].data);
+    }
+
+    #[test]
+    fn to_memory_reads_sections_and_bss() {
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x1000, data: vec![0xde, 0xad, 0xbe, 0xef], index: 0},
+                Section {kind: SectionKind::Data, address: 0x1010, data: vec![1, 2, 3, 4], index: 7},
+            ],
+            bss_start: 0x1020,
+            bss_length: 4,
+            entry_point: 0x1000,
+        };
+
+        let mem = dol.to_memory().expect("Could not flatten DOL into memory");
+
+        assert_eq!(mem.read_u32_at(0x1000).unwrap(), 0xdeadbeef);
+        assert_eq!(mem.read_bytes_at(0x1010, 4).unwrap(), &[1, 2, 3, 4]);
+        assert_eq!(mem.read_u32_at(0x1020).unwrap(), 0, "BSS should read back as zeroes");
+        assert!(mem.section_at(0x1002).is_some());
+        assert!(mem.section_at(0x1020).is_none(), "BSS is not backed by a section");
+        match mem.read_u32_at(0x2000) {
+            Err(Error::OutOfBounds(0x2000)) => {}
+            other => panic!("expected OutOfBounds(0x2000), got {:?}", other),
+        }
+    }
+
     #[test]
     fn write_dol_header() {
         use std::io::Cursor;
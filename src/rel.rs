@@ -0,0 +1,651 @@
+//! REL (relocatable module) support, the companion format to DOL used for
+//! DLC/overlay code on GameCube and Wii.
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BE};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use {DolFile, Error};
+
+/// The size, in bytes, of a REL header (version 1, without the alignment and
+/// fix-size fields added by later versions).
+const HEADER_SIZE: u32 = 0x40;
+
+/// One section in a REL file's section table. `offset` and `length` are not
+/// stored directly; they are derived from where `data` ends up laid out by
+/// [RelFile::write] and from `data.len()`, exactly as [Section] does for DOLs.
+#[derive(Debug, Clone)]
+pub struct RelSection {
+    pub executable: bool,
+    /// Byte alignment this section's data should be padded to. All known
+    /// REL sections are word-aligned, so this is currently informational
+    /// only; [RelFile::write] does not yet insert alignment padding.
+    pub alignment: u32,
+    /// This section's load address once placed in memory by a REL loader.
+    /// Unlike [Section::address] for DOLs, this is not part of the REL file
+    /// format itself (a REL's sections have no fixed address until the
+    /// loader places them at runtime), so it is not read by [RelFile::parse]
+    /// or written by [RelFile::write]. Callers that need to resolve
+    /// PC-relative relocations via [RelFile::link_against] (`Rel24`,
+    /// `Rel14`) must fill this in first, once they know where the REL has
+    /// been or will be loaded; it defaults to `0`.
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+/// One entry in a REL's import table: another module this REL holds
+/// relocations against. Module id `0` is always the main DOL.
+#[derive(Debug, Clone, Copy)]
+pub struct RelImport {
+    pub module_id: u32,
+}
+
+/// The kind of a REL relocation, matching the values used by the GameCube/Wii
+/// loader (`R_PPC_*`, plus the `R_DOLPHIN_*` pseudo-relocations used to
+/// structure the relocation stream itself).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
+    Addr32,
+    Addr24,
+    Addr16,
+    Addr16Lo,
+    Addr16Hi,
+    Addr16Ha,
+    Addr14,
+    Rel24,
+    Rel14,
+    /// Selects which of the REL's own sections subsequent entries write into.
+    DolphinSection,
+    /// Advances the offset without writing anything.
+    DolphinNop,
+    /// Terminates the relocation list for the current imported module.
+    DolphinEnd,
+}
+
+impl RelocationKind {
+    fn from_u8(value: u8) -> Result<Self, Error> {
+        Ok(match value {
+            1 => RelocationKind::Addr32,
+            2 => RelocationKind::Addr24,
+            3 => RelocationKind::Addr16,
+            4 => RelocationKind::Addr16Lo,
+            5 => RelocationKind::Addr16Hi,
+            6 => RelocationKind::Addr16Ha,
+            7 => RelocationKind::Addr14,
+            10 => RelocationKind::Rel24,
+            11 => RelocationKind::Rel14,
+            201 => RelocationKind::DolphinNop,
+            202 => RelocationKind::DolphinSection,
+            203 => RelocationKind::DolphinEnd,
+            other => return Err(Error::UnknownRelocationKind(other)),
+        })
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            RelocationKind::Addr32 => 1,
+            RelocationKind::Addr24 => 2,
+            RelocationKind::Addr16 => 3,
+            RelocationKind::Addr16Lo => 4,
+            RelocationKind::Addr16Hi => 5,
+            RelocationKind::Addr16Ha => 6,
+            RelocationKind::Addr14 => 7,
+            RelocationKind::Rel24 => 10,
+            RelocationKind::Rel14 => 11,
+            RelocationKind::DolphinNop => 201,
+            RelocationKind::DolphinSection => 202,
+            RelocationKind::DolphinEnd => 203,
+        }
+    }
+}
+
+/// A single relocation entry. `offset` is the byte offset, within whichever
+/// REL section a preceding [RelocationKind::DolphinSection] entry selected,
+/// that `target_section`'s resolved address (plus `addend`) should be written
+/// to.
+#[derive(Debug, Clone, Copy)]
+pub struct Relocation {
+    pub offset: u32,
+    pub kind: RelocationKind,
+    pub target_section: u8,
+    pub addend: u32,
+}
+
+/// A REL relocatable module file.
+///
+/// `imports` and `relocations` are parallel: `relocations[i]` is the list of
+/// relocations this REL holds against `imports[i].module_id`.
+#[derive(Debug)]
+pub struct RelFile {
+    pub module_id: u32,
+    pub sections: Vec<RelSection>,
+    pub imports: Vec<RelImport>,
+    pub relocations: Vec<Vec<Relocation>>,
+    pub bss_size: u32,
+    pub prolog_section: u8,
+    pub prolog_offset: u32,
+    pub epilog_section: u8,
+    pub epilog_offset: u32,
+    pub unresolved_section: u8,
+    pub unresolved_offset: u32,
+}
+
+impl RelFile {
+    /// Loads a REL file from a reader, returning a RelFile if successful.
+    pub fn parse<R>(rdr: &mut R) -> Result<Self, Error>
+    where
+        R: Read + Seek,
+    {
+        let module_id = rdr.read_u32::<BE>()?;
+        rdr.read_u32::<BE>()?; // next
+        rdr.read_u32::<BE>()?; // prev
+        let num_sections = rdr.read_u32::<BE>()?;
+        let section_info_offset = rdr.read_u32::<BE>()?;
+        rdr.read_u32::<BE>()?; // name_offset
+        rdr.read_u32::<BE>()?; // name_size
+        rdr.read_u32::<BE>()?; // version
+        let bss_size = rdr.read_u32::<BE>()?;
+        rdr.read_u32::<BE>()?; // rel_offset
+        let imp_offset = rdr.read_u32::<BE>()?;
+        let imp_size = rdr.read_u32::<BE>()?;
+        let prolog_section = rdr.read_u8()?;
+        let epilog_section = rdr.read_u8()?;
+        let unresolved_section = rdr.read_u8()?;
+        rdr.read_u8()?; // padding
+        let prolog_offset = rdr.read_u32::<BE>()?;
+        let epilog_offset = rdr.read_u32::<BE>()?;
+        let unresolved_offset = rdr.read_u32::<BE>()?;
+
+        rdr.seek(SeekFrom::Start(section_info_offset as u64))?;
+        let mut section_ranges = Vec::with_capacity(num_sections as usize);
+        for _ in 0..num_sections {
+            let offset_and_exec = rdr.read_u32::<BE>()?;
+            let length = rdr.read_u32::<BE>()?;
+            section_ranges.push((offset_and_exec & !1, offset_and_exec & 1 != 0, length));
+        }
+
+        let mut sections = Vec::with_capacity(section_ranges.len());
+        for (offset, executable, length) in section_ranges {
+            let mut data = vec![0u8; length as usize];
+            if offset != 0 && length != 0 {
+                rdr.seek(SeekFrom::Start(offset as u64))?;
+                rdr.read_exact(&mut data)?;
+            }
+
+            sections.push(RelSection {
+                executable,
+                alignment: 4,
+                address: 0,
+                data,
+            });
+        }
+
+        rdr.seek(SeekFrom::Start(imp_offset as u64))?;
+        let num_imports = imp_size as usize / 8;
+        let mut imports = Vec::with_capacity(num_imports);
+        let mut relocation_offsets = Vec::with_capacity(num_imports);
+        for _ in 0..num_imports {
+            imports.push(RelImport {
+                module_id: rdr.read_u32::<BE>()?,
+            });
+            relocation_offsets.push(rdr.read_u32::<BE>()?);
+        }
+
+        let mut relocations = Vec::with_capacity(imports.len());
+        for &reloc_offset in &relocation_offsets {
+            rdr.seek(SeekFrom::Start(reloc_offset as u64))?;
+
+            let mut entries = Vec::new();
+            let mut offset = 0u32;
+            loop {
+                let delta = rdr.read_u16::<BE>()?;
+                let kind = RelocationKind::from_u8(rdr.read_u8()?)?;
+                let target_section = rdr.read_u8()?;
+                let addend = rdr.read_u32::<BE>()?;
+
+                if kind == RelocationKind::DolphinEnd {
+                    break;
+                }
+
+                offset += delta as u32;
+                entries.push(Relocation { offset, kind, target_section, addend });
+
+                if kind == RelocationKind::DolphinSection {
+                    offset = 0;
+                }
+            }
+
+            relocations.push(entries);
+        }
+
+        Ok(RelFile {
+            module_id,
+            sections,
+            imports,
+            relocations,
+            bss_size,
+            prolog_section,
+            prolog_offset,
+            epilog_section,
+            epilog_offset,
+            unresolved_section,
+            unresolved_offset,
+        })
+    }
+
+    /// Writes a REL file to a writer, returning `Ok(())` if successful.
+    pub fn write<W>(&self, wtr: &mut W) -> Result<(), Error>
+    where
+        W: Write + Seek,
+    {
+        let section_info_offset = HEADER_SIZE;
+        let mut offset = section_info_offset + self.sections.len() as u32 * 8;
+
+        let mut section_offsets = Vec::with_capacity(self.sections.len());
+        for section in &self.sections {
+            if section.data.is_empty() {
+                section_offsets.push(0);
+                continue;
+            }
+            section_offsets.push(offset);
+            offset += section.data.len() as u32;
+        }
+
+        let imp_offset = offset;
+        let imp_size = self.imports.len() as u32 * 8;
+        offset += imp_size;
+
+        let mut relocation_offsets = Vec::with_capacity(self.relocations.len());
+        let mut relocation_bytes = Vec::new();
+        for entries in &self.relocations {
+            relocation_offsets.push(offset + relocation_bytes.len() as u32);
+
+            let mut prev_offset = 0u32;
+            for reloc in entries {
+                let mut delta = reloc.offset
+                    .checked_sub(prev_offset)
+                    .ok_or(Error::UnsortedRelocations)?;
+
+                // The delta is stored as a u16, so a gap wider than 0xffff
+                // between two relocations (entirely possible in a large data
+                // section) must be split across synthetic DolphinNop entries,
+                // each advancing the offset without writing anything, rather
+                // than silently truncated.
+                while delta > 0xffff {
+                    relocation_bytes.write_u16::<BE>(0xffff)?;
+                    relocation_bytes.write_u8(RelocationKind::DolphinNop.to_u8())?;
+                    relocation_bytes.write_u8(0)?;
+                    relocation_bytes.write_u32::<BE>(0)?;
+                    delta -= 0xffff;
+                }
+
+                relocation_bytes.write_u16::<BE>(delta as u16)?;
+                relocation_bytes.write_u8(reloc.kind.to_u8())?;
+                relocation_bytes.write_u8(reloc.target_section)?;
+                relocation_bytes.write_u32::<BE>(reloc.addend)?;
+
+                prev_offset = if reloc.kind == RelocationKind::DolphinSection { 0 } else { reloc.offset };
+            }
+
+            relocation_bytes.write_u16::<BE>(0)?;
+            relocation_bytes.write_u8(RelocationKind::DolphinEnd.to_u8())?;
+            relocation_bytes.write_u8(0)?;
+            relocation_bytes.write_u32::<BE>(0)?;
+        }
+
+        let rel_offset = relocation_offsets.first().cloned().unwrap_or(offset);
+
+        wtr.write_u32::<BE>(self.module_id)?;
+        wtr.write_u32::<BE>(0)?; // next
+        wtr.write_u32::<BE>(0)?; // prev
+        wtr.write_u32::<BE>(self.sections.len() as u32)?;
+        wtr.write_u32::<BE>(section_info_offset)?;
+        wtr.write_u32::<BE>(0)?; // name_offset
+        wtr.write_u32::<BE>(0)?; // name_size
+        wtr.write_u32::<BE>(1)?; // version
+        wtr.write_u32::<BE>(self.bss_size)?;
+        wtr.write_u32::<BE>(rel_offset)?;
+        wtr.write_u32::<BE>(imp_offset)?;
+        wtr.write_u32::<BE>(imp_size)?;
+        wtr.write_u8(self.prolog_section)?;
+        wtr.write_u8(self.epilog_section)?;
+        wtr.write_u8(self.unresolved_section)?;
+        wtr.write_u8(0)?; // padding
+        wtr.write_u32::<BE>(self.prolog_offset)?;
+        wtr.write_u32::<BE>(self.epilog_offset)?;
+        wtr.write_u32::<BE>(self.unresolved_offset)?;
+
+        for (section, &section_offset) in self.sections.iter().zip(&section_offsets) {
+            let exec_bit = if section.executable { 1 } else { 0 };
+            wtr.write_u32::<BE>(section_offset | exec_bit)?;
+            wtr.write_u32::<BE>(section.data.len() as u32)?;
+        }
+
+        for section in &self.sections {
+            wtr.write_all(&section.data)?;
+        }
+
+        for (import, &reloc_offset) in self.imports.iter().zip(&relocation_offsets) {
+            wtr.write_u32::<BE>(import.module_id)?;
+            wtr.write_u32::<BE>(reloc_offset)?;
+        }
+
+        wtr.write_all(&relocation_bytes)?;
+
+        Ok(())
+    }
+
+    /// Applies this REL's relocations against a loaded main DOL (module id
+    /// `0`), returning each of the REL's own sections with addresses patched
+    /// in. Relocations against any other imported module are left alone,
+    /// since resolving them requires that module to also be loaded.
+    ///
+    /// `Rel24` and `Rel14` branch relocations are PC-relative: resolving them
+    /// needs the load address of the REL section being written into, so
+    /// callers must set [RelSection::address] on every section of `self`
+    /// before calling this (e.g. after a loader has decided where the REL
+    /// will sit in memory) or those relocations will patch against the wrong
+    /// displacement.
+    pub fn link_against(&self, dol: &DolFile) -> Result<Vec<Vec<u8>>, Error> {
+        let mut linked: Vec<Vec<u8>> = self.sections.iter().map(|s| s.data.clone()).collect();
+
+        for (import, entries) in self.imports.iter().zip(&self.relocations) {
+            if import.module_id != 0 {
+                continue;
+            }
+
+            let mut write_section: Option<usize> = None;
+            for reloc in entries {
+                match reloc.kind {
+                    RelocationKind::DolphinSection => {
+                        write_section = Some(reloc.target_section as usize);
+                    }
+                    RelocationKind::DolphinNop | RelocationKind::DolphinEnd => {}
+                    _ => {
+                        // reloc.target_section numbers the DOL's fixed 18-slot
+                        // header layout, not the position a slot happens to
+                        // land at in dol.sections once empty slots are
+                        // dropped, so this must match on Section::index
+                        // rather than index into dol.sections directly.
+                        let target = dol.sections
+                            .iter()
+                            .find(|s| s.index == reloc.target_section)
+                            .ok_or(Error::UnknownRelocationSection(reloc.target_section))?;
+                        let value = target.address.wrapping_add(reloc.addend);
+
+                        let write_index = write_section
+                            .ok_or(Error::UnknownRelocationSection(reloc.target_section))?;
+                        let instruction_address = self.sections
+                            .get(write_index)
+                            .ok_or(Error::UnknownRelocationSection(reloc.target_section))?
+                            .address
+                            .wrapping_add(reloc.offset);
+                        let section = linked.get_mut(write_index)
+                            .ok_or(Error::UnknownRelocationSection(reloc.target_section))?;
+
+                        apply_relocation(section, reloc.offset as usize, reloc.kind, instruction_address, value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(linked)
+    }
+}
+
+/// Patches a single resolved relocation value into `section` at `offset`.
+/// `instruction_address` is the load address of the patched instruction
+/// itself, needed for the PC-relative `Rel24`/`Rel14` kinds.
+fn apply_relocation(
+    section: &mut [u8],
+    offset: usize,
+    kind: RelocationKind,
+    instruction_address: u32,
+    value: u32,
+) -> Result<(), Error> {
+    match kind {
+        RelocationKind::Addr32 => {
+            let target = section.get_mut(offset..offset + 4).ok_or(Error::OutOfBounds(offset as u32))?;
+            BE::write_u32(target, value);
+        }
+        RelocationKind::Addr16 | RelocationKind::Addr16Lo => {
+            let target = section.get_mut(offset..offset + 2).ok_or(Error::OutOfBounds(offset as u32))?;
+            BE::write_u16(target, value as u16);
+        }
+        RelocationKind::Addr16Hi => {
+            let target = section.get_mut(offset..offset + 2).ok_or(Error::OutOfBounds(offset as u32))?;
+            BE::write_u16(target, (value >> 16) as u16);
+        }
+        RelocationKind::Addr16Ha => {
+            let carry = if value & 0x8000 != 0 { 1 } else { 0 };
+            let target = section.get_mut(offset..offset + 2).ok_or(Error::OutOfBounds(offset as u32))?;
+            BE::write_u16(target, ((value >> 16) as u16).wrapping_add(carry));
+        }
+        // Absolute 24-bit field of a `b`/`bl` instruction (opcode 18): bits
+        // 6-29 hold the word-aligned displacement, shifted right by 2: the
+        // low 2 bits of the field mask are always zero, so they're folded in
+        // unconditionally below. Opcode (top 6 bits) and AA/LK (bottom 2
+        // bits) are preserved.
+        RelocationKind::Addr24 => {
+            let target = section.get_mut(offset..offset + 4).ok_or(Error::OutOfBounds(offset as u32))?;
+            let instruction = BE::read_u32(target);
+            BE::write_u32(target, (instruction & 0xfc00_0003) | (value & 0x03ff_fffc));
+        }
+        // Same field as Addr24, but PC-relative: the displacement is the
+        // distance from this instruction to the target, not the target's
+        // absolute address.
+        RelocationKind::Rel24 => {
+            let target = section.get_mut(offset..offset + 4).ok_or(Error::OutOfBounds(offset as u32))?;
+            let instruction = BE::read_u32(target);
+            let displacement = value.wrapping_sub(instruction_address);
+            BE::write_u32(target, (instruction & 0xfc00_0003) | (displacement & 0x03ff_fffc));
+        }
+        // Absolute 14-bit field of a conditional branch (`bc`, opcode 16):
+        // bits 16-29 hold the word-aligned displacement, shifted right by 2.
+        // Opcode/BO/BI (top 16 bits) and AA/LK (bottom 2 bits) are preserved.
+        RelocationKind::Addr14 => {
+            let target = section.get_mut(offset..offset + 4).ok_or(Error::OutOfBounds(offset as u32))?;
+            let instruction = BE::read_u32(target);
+            BE::write_u32(target, (instruction & 0xffff_0003) | (value & 0x0000_fffc));
+        }
+        // Same field as Addr14, but PC-relative, like Rel24 is to Addr24.
+        RelocationKind::Rel14 => {
+            let target = section.get_mut(offset..offset + 4).ok_or(Error::OutOfBounds(offset as u32))?;
+            let instruction = BE::read_u32(target);
+            let displacement = value.wrapping_sub(instruction_address);
+            BE::write_u32(target, (instruction & 0xffff_0003) | (displacement & 0x0000_fffc));
+        }
+        RelocationKind::DolphinSection | RelocationKind::DolphinNop | RelocationKind::DolphinEnd => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+    use {Section, SectionKind};
+
+    #[test]
+    fn write_back_identical_rel() {
+        let rel = RelFile {
+            module_id: 5,
+            sections: vec![
+                RelSection {executable: true, alignment: 4, address: 0, data: vec![0; 0]},
+                RelSection {executable: true, alignment: 4, address: 0, data: vec![0x60, 0x00, 0x00, 0x00]},
+                RelSection {executable: false, alignment: 4, address: 0, data: vec![1, 2, 3, 4]},
+            ],
+            imports: vec![RelImport {module_id: 0}],
+            relocations: vec![vec![
+                Relocation {offset: 0, kind: RelocationKind::DolphinSection, target_section: 1, addend: 0},
+                Relocation {offset: 0, kind: RelocationKind::Addr32, target_section: 0, addend: 0},
+            ]],
+            bss_size: 0,
+            prolog_section: 1,
+            prolog_offset: 0,
+            epilog_section: 1,
+            epilog_offset: 0,
+            unresolved_section: 1,
+            unresolved_offset: 0,
+        };
+
+        let mut buf = Cursor::new(Vec::new());
+        rel.write(&mut buf).expect("Could not write REL file");
+
+        let mut buf = Cursor::new(buf.into_inner());
+        let roundtripped = RelFile::parse(&mut buf).expect("Could not parse REL file");
+
+        assert_eq!(roundtripped.module_id, rel.module_id);
+        assert_eq!(roundtripped.sections.len(), rel.sections.len());
+        assert_eq!(roundtripped.sections[1].data, rel.sections[1].data);
+        assert_eq!(roundtripped.relocations[0].len(), rel.relocations[0].len());
+    }
+
+    #[test]
+    fn write_splits_large_relocation_gaps_into_dolphin_nops() {
+        // Two relocations in the same run, 0x20000 (> 0xffff twice over)
+        // bytes apart: the delta must come back out as the original offset
+        // after being split across synthetic DolphinNop entries, not
+        // truncated to a wrong, smaller offset by a bare `as u16` cast.
+        let rel = RelFile {
+            module_id: 5,
+            sections: vec![
+                RelSection {executable: true, alignment: 4, address: 0, data: vec![0; 0x20004]},
+            ],
+            imports: vec![RelImport {module_id: 0}],
+            relocations: vec![vec![
+                Relocation {offset: 0, kind: RelocationKind::DolphinSection, target_section: 0, addend: 0},
+                Relocation {offset: 0, kind: RelocationKind::Addr32, target_section: 0, addend: 0},
+                Relocation {offset: 0x20000, kind: RelocationKind::Addr32, target_section: 0, addend: 4},
+            ]],
+            bss_size: 0,
+            prolog_section: 0,
+            prolog_offset: 0,
+            epilog_section: 0,
+            epilog_offset: 0,
+            unresolved_section: 0,
+            unresolved_offset: 0,
+        };
+
+        let mut buf = Cursor::new(Vec::new());
+        rel.write(&mut buf).expect("Could not write REL file");
+
+        let mut buf = Cursor::new(buf.into_inner());
+        let roundtripped = RelFile::parse(&mut buf).expect("Could not parse REL file");
+
+        let last = roundtripped.relocations[0]
+            .last()
+            .expect("expected at least one relocation");
+        assert_eq!(last.kind, RelocationKind::Addr32);
+        assert_eq!(last.offset, 0x20000);
+    }
+
+    #[test]
+    fn link_against_patches_dol_addresses() {
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x8000_1000, data: vec![0; 4], index: 0},
+            ],
+            bss_start: 0, bss_length: 0,
+            entry_point: 0x8000_1000,
+        };
+
+        let rel = RelFile {
+            module_id: 1,
+            sections: vec![
+                RelSection {executable: false, alignment: 4, address: 0, data: vec![0; 0]},
+                RelSection {executable: true, alignment: 4, address: 0, data: vec![0; 4]},
+            ],
+            imports: vec![RelImport {module_id: 0}],
+            relocations: vec![vec![
+                Relocation {offset: 0, kind: RelocationKind::DolphinSection, target_section: 1, addend: 0},
+                Relocation {offset: 0, kind: RelocationKind::Addr32, target_section: 0, addend: 4},
+            ]],
+            bss_size: 0,
+            prolog_section: 1, prolog_offset: 0,
+            epilog_section: 1, epilog_offset: 0,
+            unresolved_section: 1, unresolved_offset: 0,
+        };
+
+        let linked = rel.link_against(&dol).expect("Could not link REL against DOL");
+        assert_eq!(linked[1], vec![0x80, 0x00, 0x10, 0x04]);
+    }
+
+    #[test]
+    fn link_against_resolves_target_section_by_dol_header_slot() {
+        // A DOL with only one text section (slot 0) and one data section in
+        // the *second* data slot (slot 8, not 7): dol.sections is therefore
+        // [slot 0, slot 8], a compacted Vec where position 1 does not equal
+        // header slot 1. A relocation against header slot 8 must resolve to
+        // the data section, not (as a naive `dol.sections.get(8)` would) miss
+        // entirely.
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x8000_1000, data: vec![0; 4], index: 0},
+                Section {kind: SectionKind::Data, address: 0x8000_3000, data: vec![0; 4], index: 8},
+            ],
+            bss_start: 0, bss_length: 0,
+            entry_point: 0x8000_1000,
+        };
+
+        let rel = RelFile {
+            module_id: 1,
+            sections: vec![
+                RelSection {executable: true, alignment: 4, address: 0, data: vec![0; 4]},
+            ],
+            imports: vec![RelImport {module_id: 0}],
+            relocations: vec![vec![
+                Relocation {offset: 0, kind: RelocationKind::DolphinSection, target_section: 0, addend: 0},
+                Relocation {offset: 0, kind: RelocationKind::Addr32, target_section: 8, addend: 0},
+            ]],
+            bss_size: 0,
+            prolog_section: 0, prolog_offset: 0,
+            epilog_section: 0, epilog_offset: 0,
+            unresolved_section: 0, unresolved_offset: 0,
+        };
+
+        let linked = rel.link_against(&dol).expect("Could not link REL against DOL");
+        assert_eq!(linked[0], vec![0x80, 0x00, 0x30, 0x00]);
+    }
+
+    #[test]
+    fn link_against_patches_rel24_pc_relative_branch() {
+        let dol = DolFile {
+            sections: vec![
+                Section {kind: SectionKind::Text, address: 0x8000_2000, data: vec![0; 4], index: 0},
+            ],
+            bss_start: 0, bss_length: 0,
+            entry_point: 0x8000_2000,
+        };
+
+        // An executable REL section loaded at 0x8000_1000, containing a
+        // single `bl` instruction (opcode 18, LK set) at offset 0 whose
+        // branch-displacement field starts as all zero.
+        let rel = RelFile {
+            module_id: 1,
+            sections: vec![
+                RelSection {
+                    executable: true,
+                    alignment: 4,
+                    address: 0x8000_1000,
+                    data: vec![0x48, 0x00, 0x00, 0x01],
+                },
+            ],
+            imports: vec![RelImport {module_id: 0}],
+            relocations: vec![vec![
+                Relocation {offset: 0, kind: RelocationKind::DolphinSection, target_section: 0, addend: 0},
+                Relocation {offset: 0, kind: RelocationKind::Rel24, target_section: 0, addend: 0},
+            ]],
+            bss_size: 0,
+            prolog_section: 0, prolog_offset: 0,
+            epilog_section: 0, epilog_offset: 0,
+            unresolved_section: 0, unresolved_offset: 0,
+        };
+
+        let linked = rel.link_against(&dol).expect("Could not link REL against DOL");
+
+        // Displacement is 0x8000_2000 - 0x8000_1000 = 0x1000; opcode (18)
+        // and LK (bit 0) from the original instruction must be preserved.
+        assert_eq!(linked[0], vec![0x48, 0x00, 0x10, 0x01]);
+    }
+}
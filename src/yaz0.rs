@@ -0,0 +1,201 @@
+//! Yaz0, the run-length/LZ compression scheme used to pack GameCube and Wii
+//! DOLs (and other assets) inside archives.
+
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use Error;
+
+/// The 4-byte magic identifying a Yaz0-compressed buffer.
+pub const MAGIC: &[u8; 4] = b"Yaz0";
+
+/// The maximum distance a back-reference can reach into the already-produced
+/// output (12 bits, plus one).
+const MAX_DISTANCE: usize = 0x1000;
+
+/// The maximum length of a single back-reference (a 3-byte group's length
+/// nibble of 0 plus a byte 0xff, plus the implicit `+ 0x12`).
+const MAX_LENGTH: usize = 0x111;
+
+/// Decompresses a Yaz0-compressed buffer, returning the decompressed bytes.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut rdr = Cursor::new(data);
+
+    let mut magic = [0u8; 4];
+    rdr.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::NotYaz0);
+    }
+
+    let decompressed_size = rdr.read_u32::<BE>()? as usize;
+    rdr.seek(SeekFrom::Current(8))?; // reserved
+
+    let mut out = Vec::with_capacity(decompressed_size);
+
+    while out.len() < decompressed_size {
+        let code = rdr.read_u8()?;
+
+        for bit in (0..8).rev() {
+            if out.len() >= decompressed_size {
+                break;
+            }
+
+            if code & (1 << bit) != 0 {
+                out.push(rdr.read_u8()?);
+                continue;
+            }
+
+            let b0 = rdr.read_u8()?;
+            let b1 = rdr.read_u8()?;
+            let distance = (((b0 & 0x0f) as usize) << 8 | b1 as usize) + 1;
+            let length = if b0 >> 4 == 0 {
+                rdr.read_u8()? as usize + 0x12
+            } else {
+                (b0 >> 4) as usize + 2
+            };
+
+            if distance > out.len() {
+                return Err(Error::InvalidBackReference(distance));
+            }
+
+            let start = out.len() - distance;
+            let length = length.min(decompressed_size - out.len());
+            for i in 0..length {
+                let byte = out[start + i];
+                out.push(byte);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Compresses a buffer with Yaz0, using a simple greedy longest-match search.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.write_u32::<BE>(data.len() as u32).unwrap();
+    out.extend_from_slice(&[0u8; 8]); // reserved
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut code = 0u8;
+        let mut group = Vec::new();
+
+        for bit in (0..8).rev() {
+            if pos >= data.len() {
+                break;
+            }
+
+            match find_longest_match(data, pos) {
+                Some((distance, length)) => {
+                    if length < 0x12 {
+                        group.push((((length - 2) as u8) << 4) | ((distance - 1) >> 8) as u8);
+                        group.push(((distance - 1) & 0xff) as u8);
+                    } else {
+                        group.push(((distance - 1) >> 8) as u8);
+                        group.push(((distance - 1) & 0xff) as u8);
+                        group.push((length - 0x12) as u8);
+                    }
+                    pos += length;
+                }
+                None => {
+                    code |= 1 << bit;
+                    group.push(data[pos]);
+                    pos += 1;
+                }
+            }
+        }
+
+        out.push(code);
+        out.extend_from_slice(&group);
+    }
+
+    out
+}
+
+/// Finds the longest back-reference match (distance, length) for
+/// `data[pos..]` within the preceding `MAX_DISTANCE` bytes, if any match of at
+/// least 3 bytes exists.
+fn find_longest_match(data: &[u8], pos: usize) -> Option<(usize, usize)> {
+    let window_start = pos.saturating_sub(MAX_DISTANCE);
+    let max_length = (data.len() - pos).min(MAX_LENGTH);
+
+    let mut best: Option<(usize, usize)> = None;
+
+    for start in window_start..pos {
+        let mut length = 0;
+        while length < max_length && data[start + length] == data[pos + length] {
+            length += 1;
+        }
+
+        let is_better = match best {
+            Some((_, best_len)) => length > best_len,
+            None => true,
+        };
+
+        if length >= 3 && is_better {
+            best = Some((pos - start, length));
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips_arbitrary_data() {
+        let original: Vec<u8> = (0..2000).map(|i| (i % 17) as u8).collect();
+
+        let compressed = compress(&original);
+        assert_eq!(&compressed[0..4], MAGIC);
+
+        let decompressed = decompress(&compressed).expect("Could not decompress");
+        assert_eq!(decompressed, original);
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let data = [0u8; 16];
+        match decompress(&data) {
+            Err(Error::NotYaz0) => {}
+            other => panic!("expected NotYaz0, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_back_reference() {
+        // "Yaz0" + decompressed size (4) + 8 reserved bytes, then one group
+        // byte (0x00, all back-references) whose single short-form
+        // back-reference (10 00, distance 1, length 3) is read before any
+        // output has been produced.
+        let data: &[u8] = &[
+            b'Y', b'a', b'z', b'0', 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0x10, 0x00,
+        ];
+
+        match decompress(data) {
+            Err(Error::InvalidBackReference(1)) => {}
+            other => panic!("expected InvalidBackReference(1), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn clamps_back_reference_overshooting_decompressed_size() {
+        // "Yaz0" + decompressed size (3) + 8 reserved bytes, then one group
+        // byte (0x80: one literal, then one back-reference) whose literal
+        // 'A' is followed by a length-5 back-reference at distance 1 -
+        // copying all 5 bytes would produce 6 bytes total, overshooting the
+        // declared decompressed size of 3. This crate's own `compress` never
+        // emits a match that overshoots, so only a hand-crafted buffer like
+        // this one exercises the clamp.
+        let data: &[u8] = &[
+            b'Y', b'a', b'z', b'0', 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, b'A', 0x30, 0x00,
+        ];
+
+        let decompressed = decompress(data).expect("Could not decompress");
+        assert_eq!(decompressed, b"AAA");
+    }
+}